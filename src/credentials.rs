@@ -0,0 +1,24 @@
+use tokio::net::unix::UCred;
+
+/// Credentials of the process on the other end of an
+/// [`Endpoint::Unix`](crate::Endpoint::Unix) connection, captured via
+/// `SO_PEERCRED` and injected into each request as an axum `Extension`.
+///
+/// Absent for [`Endpoint::Inet`](crate::Endpoint::Inet) connections, since
+/// TCP has no equivalent notion of a local peer process.
+#[derive(Debug, Clone, Copy)]
+pub struct PeerCredentials {
+    pub pid: Option<i32>,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl From<UCred> for PeerCredentials {
+    fn from(cred: UCred) -> Self {
+        Self {
+            pid: cred.pid(),
+            uid: cred.uid(),
+            gid: cred.gid(),
+        }
+    }
+}
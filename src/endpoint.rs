@@ -1,21 +1,54 @@
-use nix::unistd;
+use nix::{
+    errno::Errno,
+    fcntl::{flock, FlockArg},
+    unistd,
+};
 #[cfg(feature = "serde")]
 use serde::Deserialize;
 use std::{
     convert::Infallible,
-    fs::{set_permissions, Permissions},
-    os::unix::fs::{self, PermissionsExt},
+    fs::{self as stdfs, set_permissions, File, Permissions},
+    io,
+    os::unix::{
+        fs::{self, PermissionsExt},
+        io::AsRawFd,
+    },
     path::PathBuf,
     result,
     str::FromStr,
 };
 
+use crate::systemd::{self, InheritedSocket};
+#[cfg(feature = "tls")]
+use crate::tls::TlsConfig;
+
 type Result = result::Result<(), String>;
 
 #[derive(Debug)]
 pub enum Endpoint {
-    Inet(String),
+    Inet(Inet),
     Unix(UnixDomainSocket),
+
+    /// A socket inherited from an init system, adopted as-is instead of
+    /// bound by this process. See [`Endpoint::from_systemd`].
+    Systemd(InheritedSocket),
+}
+
+impl Endpoint {
+    /// Adopts every socket passed to this process by an init system via
+    /// the systemd socket activation protocol (`LISTEN_FDS`/
+    /// `LISTEN_PID`), returning one [`Endpoint::Systemd`] per inherited
+    /// file descriptor.
+    ///
+    /// Returns an empty `Vec` if the process was not started under
+    /// socket activation, so it's safe to append the result to a list
+    /// of statically configured endpoints unconditionally.
+    pub fn from_systemd() -> result::Result<Vec<Self>, String> {
+        Ok(systemd::inherited_sockets()?
+            .into_iter()
+            .map(Self::Systemd)
+            .collect())
+    }
 }
 
 impl FromStr for Endpoint {
@@ -25,11 +58,34 @@ impl FromStr for Endpoint {
         if s.starts_with('/') {
             Ok(Self::Unix(s.parse().unwrap()))
         } else {
-            Ok(Self::Inet(s.to_string()))
+            Ok(Self::Inet(s.parse().unwrap()))
         }
     }
 }
 
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Inet {
+    pub addr: String,
+
+    /// Certificate and private key used to terminate TLS for connections
+    /// to this endpoint. Connections are served in plaintext when absent.
+    #[cfg(feature = "tls")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tls: Option<TlsConfig>,
+}
+
+impl FromStr for Inet {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        Ok(Self {
+            addr: s.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 #[cfg_attr(feature = "serde", derive(Deserialize))]
 pub struct UnixDomainSocket {
@@ -37,9 +93,71 @@ pub struct UnixDomainSocket {
     pub mode: Option<u32>,
     pub owner: Option<String>,
     pub group: Option<String>,
+
+    /// Remove a pre-existing socket file left behind by a crashed
+    /// previous run before binding, instead of failing. Guarded by an
+    /// advisory lock on a sibling `<path>.lock` file so two processes
+    /// racing to restart don't both try to delete/recreate the socket.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reuse: bool,
 }
 
 impl UnixDomainSocket {
+    fn lock_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".lock");
+        PathBuf::from(path)
+    }
+
+    /// When `reuse` is set, acquires an advisory lock on this socket's
+    /// lock file and removes any pre-existing socket file, returning the
+    /// open lock file to be held for the lifetime of the listener.
+    ///
+    /// Returns `Ok(None)` without touching the filesystem when `reuse`
+    /// is not set, preserving the default behavior of failing to bind
+    /// over an existing socket file.
+    pub(crate) fn lock_for_reuse(&self) -> result::Result<Option<File>, String> {
+        if !self.reuse {
+            return Ok(None);
+        }
+
+        let lock_path = self.lock_path();
+        let lock_file = File::create(&lock_path).map_err(|err| {
+            format!(
+                "Failed to open lock file '{}': {err}",
+                lock_path.display()
+            )
+        })?;
+
+        flock(lock_file.as_raw_fd(), FlockArg::LockExclusiveNonblock)
+            .map_err(|err| match err {
+                Errno::EWOULDBLOCK => format!(
+                    "Socket '{}' is in use by another process holding \
+                    lock file '{}'",
+                    self.path.display(),
+                    lock_path.display()
+                ),
+                err => format!(
+                    "Failed to lock '{}': {err}",
+                    lock_path.display()
+                ),
+            })?;
+
+        match stdfs::remove_file(&self.path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(format!(
+                    "Failed to remove stale Unix domain socket file \
+                    '{}': {err}",
+                    self.path.display()
+                ))
+            }
+        }
+
+        Ok(Some(lock_file))
+    }
+
     fn chmod(&self) -> Result {
         if let Some(permissions) = self.mode.map(Permissions::from_mode) {
             set_permissions(&self.path, permissions).map_err(|err| {
@@ -120,3 +238,94 @@ impl FromStr for UnixDomainSocket {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::UnixDomainSocket;
+    use std::{
+        fs,
+        path::PathBuf,
+        sync::atomic::{AtomicU32, Ordering},
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    fn temp_socket_path() -> PathBuf {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        std::env::temp_dir()
+            .join(format!("axum-unix-test-{nanos}-{count}.sock"))
+    }
+
+    #[test]
+    fn lock_path_appends_suffix() {
+        let uds = UnixDomainSocket {
+            path: PathBuf::from("/tmp/app.sock"),
+            ..Default::default()
+        };
+
+        assert_eq!(uds.lock_path(), PathBuf::from("/tmp/app.sock.lock"));
+    }
+
+    #[test]
+    fn lock_for_reuse_is_a_noop_when_disabled() {
+        let path = temp_socket_path();
+        fs::write(&path, b"").unwrap();
+
+        let uds = UnixDomainSocket {
+            path: path.clone(),
+            reuse: false,
+            ..Default::default()
+        };
+
+        assert!(uds.lock_for_reuse().unwrap().is_none());
+        assert!(path.exists());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn lock_for_reuse_removes_a_stale_socket_file() {
+        let path = temp_socket_path();
+        fs::write(&path, b"").unwrap();
+
+        let uds = UnixDomainSocket {
+            path: path.clone(),
+            reuse: true,
+            ..Default::default()
+        };
+
+        assert!(uds.lock_for_reuse().unwrap().is_some());
+        assert!(!path.exists());
+
+        fs::remove_file(uds.lock_path()).unwrap();
+    }
+
+    #[test]
+    fn lock_for_reuse_rejects_a_concurrent_holder() {
+        let path = temp_socket_path();
+
+        let first = UnixDomainSocket {
+            path: path.clone(),
+            reuse: true,
+            ..Default::default()
+        };
+        let second = UnixDomainSocket {
+            path,
+            reuse: true,
+            ..Default::default()
+        };
+
+        let lock = first.lock_for_reuse().unwrap();
+        assert!(lock.is_some());
+        assert!(second.lock_for_reuse().is_err());
+
+        drop(lock);
+        fs::remove_file(first.lock_path()).unwrap();
+    }
+}
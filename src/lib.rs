@@ -1,8 +1,16 @@
+mod credentials;
 mod endpoint;
 mod serde;
 mod serve;
 mod signal;
+mod systemd;
+#[cfg(feature = "tls")]
+mod tls;
 
-pub use endpoint::{Endpoint, UnixDomainSocket};
-pub use serve::serve;
+pub use credentials::PeerCredentials;
+pub use endpoint::{Endpoint, Inet, UnixDomainSocket};
+pub use serve::{serve, serve_all, DEFAULT_SHUTDOWN_TIMEOUT};
 pub use signal::shutdown_signal;
+pub use systemd::InheritedSocket;
+#[cfg(feature = "tls")]
+pub use tls::{CertAndKey, CertResolver, TlsConfig};
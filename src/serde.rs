@@ -1,22 +1,48 @@
 #![cfg(feature = "serde")]
 
-use crate::Endpoint;
+use crate::{Endpoint, Inet, UnixDomainSocket};
 
 use serde::{
-    de::{
-        value::MapAccessDeserializer, Deserialize, Error, MapAccess, Visitor,
-    },
-    ser::{Serialize, SerializeMap, Serializer},
+    de::{value::MapAccessDeserializer, Error, MapAccess, Visitor},
+    ser::{Error as SerError, SerializeMap, Serializer},
+    Deserialize, Serialize,
 };
 use std::fmt;
 
+/// Disambiguates the two map forms an [`Endpoint`] can be deserialized
+/// from: an [`Inet`] config keyed by `addr`, or a [`UnixDomainSocket`]
+/// config keyed by `path`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EndpointMap {
+    Inet(Inet),
+    Unix(UnixDomainSocket),
+}
+
 impl Serialize for Endpoint {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
         match self {
-            Endpoint::Inet(inet) => serializer.serialize_str(inet),
+            #[cfg(feature = "tls")]
+            Endpoint::Inet(inet) if inet.tls.is_none() => {
+                serializer.serialize_str(&inet.addr)
+            }
+            #[cfg(feature = "tls")]
+            Endpoint::Inet(inet) => {
+                let mut map = serializer.serialize_map(None)?;
+
+                map.serialize_entry("addr", &inet.addr)?;
+
+                if let Some(ref tls) = inet.tls {
+                    map.serialize_entry("tls", tls)?;
+                }
+
+                map.end()
+            }
+            #[cfg(not(feature = "tls"))]
+            Endpoint::Inet(inet) => serializer.serialize_str(&inet.addr),
             Endpoint::Unix(uds) => {
                 if uds.mode.is_none()
                     && uds.owner.is_none()
@@ -43,6 +69,9 @@ impl Serialize for Endpoint {
                     map.end()
                 }
             }
+            Endpoint::Systemd(_) => Err(S::Error::custom(
+                "an inherited systemd socket cannot be serialized",
+            )),
         }
     }
 }
@@ -66,9 +95,11 @@ impl<'de> Deserialize<'de> for Endpoint {
                 M: MapAccess<'de>,
             {
                 let deserializer = MapAccessDeserializer::new(map);
-                let uds = Deserialize::deserialize(deserializer)?;
 
-                Ok(Self::Value::Unix(uds))
+                Ok(match EndpointMap::deserialize(deserializer)? {
+                    EndpointMap::Inet(inet) => Self::Value::Inet(inet),
+                    EndpointMap::Unix(uds) => Self::Value::Unix(uds),
+                })
             }
 
             fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
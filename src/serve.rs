@@ -1,4 +1,8 @@
-use crate::endpoint::{Endpoint, UnixDomainSocket};
+use crate::credentials::PeerCredentials;
+use crate::endpoint::{Endpoint, Inet, UnixDomainSocket};
+use crate::systemd::InheritedSocket;
+#[cfg(feature = "tls")]
+use crate::tls::TlsConfig;
 
 use axum::{extract::Request, Router};
 use futures_util::{pin_mut, FutureExt};
@@ -8,15 +12,31 @@ use hyper_util::{
     server::conn::auto::Builder,
 };
 use log::{error, info, log_enabled, trace, warn, Level::Trace};
+use std::future::Future;
+use std::io;
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 use tokio::{
-    net::{unix, TcpListener, UnixListener},
+    io::{AsyncRead, AsyncWrite},
+    net::{unix, TcpListener, TcpStream, UnixListener, UnixStream},
     task::JoinHandle,
+    time,
 };
 use tokio_util::{net::Listener, sync::CancellationToken, task::TaskTracker};
 use tower::Service;
 
+/// Default grace period given to in-flight connections to finish after a
+/// shutdown signal before they're force-closed. See [`serve`].
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Deadline given to a client to complete a TLS handshake before the
+/// connection is dropped, so a trickle of clients that open a connection
+/// and never finish (or never start) the handshake can't tie up
+/// connection tasks indefinitely.
+#[cfg(feature = "tls")]
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
 struct PathGuard(PathBuf);
 
 impl Drop for PathGuard {
@@ -51,11 +71,39 @@ impl DisplayAddr for unix::SocketAddr {
     }
 }
 
-async fn listen<T>(mut listener: T, app: Router, token: CancellationToken)
-where
+/// Captures the peer credentials available for a connection, if any.
+///
+/// Only Unix domain sockets carry `SO_PEERCRED` information; TCP
+/// connections have no local process to attribute the connection to.
+trait Credentials {
+    fn peer_credentials(&self) -> Option<PeerCredentials>;
+}
+
+impl Credentials for TcpStream {
+    fn peer_credentials(&self) -> Option<PeerCredentials> {
+        None
+    }
+}
+
+impl Credentials for UnixStream {
+    fn peer_credentials(&self) -> Option<PeerCredentials> {
+        self.peer_cred().ok().map(PeerCredentials::from)
+    }
+}
+
+async fn listen<T, W, Fut, Io>(
+    mut listener: T,
+    app: Router,
+    token: CancellationToken,
+    wrap: W,
+    shutdown_timeout: Duration,
+) where
     T: Listener,
     T::Addr: DisplayAddr,
-    T::Io: Send + Unpin + 'static,
+    T::Io: Credentials + Send + Unpin + 'static,
+    W: Fn(T::Io) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = io::Result<Io>> + Send,
+    Io: AsyncRead + AsyncWrite + Send + Unpin + 'static,
 {
     let tracker = TaskTracker::new();
 
@@ -81,13 +129,27 @@ where
             None => trace!("Connection accepted"),
         };
 
-        let socket = TokioIo::new(socket);
+        let credentials = socket.peer_credentials();
         let tower_service = app.clone();
         let cloned_token = token.clone();
+        let wrap = wrap.clone();
 
         tracker.spawn(async move {
+            let socket = match wrap(socket).await {
+                Ok(socket) => socket,
+                Err(err) => {
+                    warn!("Failed to set up connection: {err}");
+                    return;
+                }
+            };
+            let socket = TokioIo::new(socket);
+
             let hyper_service =
-                service_fn(move |request: Request<Incoming>| {
+                service_fn(move |mut request: Request<Incoming>| {
+                    if let Some(credentials) = credentials {
+                        request.extensions_mut().insert(credentials);
+                    }
+
                     tower_service.clone().call(request)
                 });
 
@@ -99,20 +161,30 @@ where
             let cancellation = cloned_token.cancelled().fuse();
             pin_mut!(cancellation);
 
-            loop {
-                tokio::select! {
-                    result = connection.as_mut() => {
-                        if let Err(err) = result {
+            tokio::select! {
+                result = connection.as_mut() => {
+                    if let Err(err) = result {
+                        error!("Failed to serve connection: {err}");
+                    }
+                }
+                _ = &mut cancellation => {
+                    trace!(
+                        "Cancellation requested for connection task, \
+                        starting graceful shutdown"
+                    );
+                    connection.as_mut().graceful_shutdown();
+
+                    match time::timeout(shutdown_timeout, connection.as_mut())
+                        .await
+                    {
+                        Ok(Ok(())) => {}
+                        Ok(Err(err)) => {
                             error!("Failed to serve connection: {err}");
                         }
-                        break;
-                    }
-                    _ = &mut cancellation => {
-                        trace!(
-                            "Cancellation requested for connection task, \
-                            starting graceful shutdown"
-                        );
-                        connection.as_mut().graceful_shutdown();
+                        Err(_) => warn!(
+                            "Connection did not finish graceful shutdown \
+                            within {shutdown_timeout:?}, force-closing it"
+                        ),
                     }
                 }
             }
@@ -142,14 +214,16 @@ where
 }
 
 async fn serve_inet<F>(
-    addr: &str,
+    inet: &Inet,
     app: Router,
     token: CancellationToken,
     f: F,
+    shutdown_timeout: Duration,
 ) -> Result<JoinHandle<()>, String>
 where
     F: FnOnce(Option<SocketAddr>),
 {
+    let addr = inet.addr.as_str();
     let listener = TcpListener::bind(addr)
         .await
         .map_err(|err| format!("failed to bind to address '{addr}': {err}"))?;
@@ -166,8 +240,64 @@ where
         }
     };
 
+    #[cfg(feature = "tls")]
+    let acceptor = inet.tls.as_ref().map(TlsConfig::acceptor).transpose()?;
+
+    #[cfg(feature = "tls")]
+    let handle = tokio::spawn(async move {
+        match acceptor {
+            Some(acceptor) => {
+                listen(
+                    listener,
+                    app,
+                    token,
+                    move |socket| {
+                        let acceptor = acceptor.clone();
+                        async move {
+                            match time::timeout(
+                                TLS_HANDSHAKE_TIMEOUT,
+                                acceptor.accept(socket),
+                            )
+                            .await
+                            {
+                                Ok(result) => result,
+                                Err(_) => Err(io::Error::new(
+                                    io::ErrorKind::TimedOut,
+                                    format!(
+                                        "TLS handshake did not complete \
+                                        within {TLS_HANDSHAKE_TIMEOUT:?}"
+                                    ),
+                                )),
+                            }
+                        }
+                    },
+                    shutdown_timeout,
+                )
+                .await;
+            }
+            None => {
+                listen(
+                    listener,
+                    app,
+                    token,
+                    |socket| async move { Ok(socket) },
+                    shutdown_timeout,
+                )
+                .await;
+            }
+        }
+    });
+
+    #[cfg(not(feature = "tls"))]
     let handle = tokio::spawn(async move {
-        listen(listener, app, token).await;
+        listen(
+            listener,
+            app,
+            token,
+            |socket| async move { Ok(socket) },
+            shutdown_timeout,
+        )
+        .await;
     });
 
     Ok(handle)
@@ -178,11 +308,14 @@ async fn serve_unix<F>(
     app: Router,
     token: CancellationToken,
     f: F,
+    shutdown_timeout: Duration,
 ) -> Result<JoinHandle<()>, String>
 where
     F: FnOnce(Option<SocketAddr>),
 {
     let path = uds.path.as_path();
+    let lock = uds.lock_for_reuse()?;
+
     let listener = UnixListener::bind(path).map_err(|err| {
         format!(
             "failed to bind to Unix domain socket path '{}': {err}",
@@ -197,24 +330,156 @@ where
     f(None);
 
     let handle = tokio::spawn(async move {
-        listen(listener, app, token).await;
+        listen(
+            listener,
+            app,
+            token,
+            |socket| async move { Ok(socket) },
+            shutdown_timeout,
+        )
+        .await;
         drop(guard);
+        drop(lock);
     });
 
     Ok(handle)
 }
 
+async fn serve_systemd<F>(
+    socket: InheritedSocket,
+    app: Router,
+    token: CancellationToken,
+    f: F,
+    shutdown_timeout: Duration,
+) -> Result<JoinHandle<()>, String>
+where
+    F: FnOnce(Option<SocketAddr>),
+{
+    match socket {
+        InheritedSocket::Inet(listener) => {
+            match listener.local_addr() {
+                Ok(addr) => {
+                    info!(
+                        "Listening for connections on {addr} (inherited)"
+                    );
+                    f(Some(addr));
+                }
+                Err(err) => {
+                    warn!(
+                        "Could not retrieve inherited TCP listener's \
+                        local address: {err}"
+                    );
+                    f(None);
+                }
+            }
+
+            Ok(tokio::spawn(async move {
+                listen(
+                    listener,
+                    app,
+                    token,
+                    |socket| async move { Ok(socket) },
+                    shutdown_timeout,
+                )
+                .await;
+            }))
+        }
+        InheritedSocket::Unix(listener) => {
+            info!(
+                "Listening for connections on inherited Unix domain socket"
+            );
+            f(None);
+
+            Ok(tokio::spawn(async move {
+                listen(
+                    listener,
+                    app,
+                    token,
+                    |socket| async move { Ok(socket) },
+                    shutdown_timeout,
+                )
+                .await;
+            }))
+        }
+    }
+}
+
+/// Serves `app` over `endpoint` until `token` is cancelled.
+///
+/// `shutdown_timeout` bounds how long in-flight connections are given to
+/// finish gracefully after cancellation; connections still open once it
+/// elapses are force-closed. Use [`DEFAULT_SHUTDOWN_TIMEOUT`] for a sane
+/// default.
 pub async fn serve<F>(
-    endpoint: &Endpoint,
+    endpoint: Endpoint,
     app: Router,
     token: CancellationToken,
     f: F,
+    shutdown_timeout: Duration,
 ) -> Result<JoinHandle<()>, String>
 where
     F: FnOnce(Option<SocketAddr>),
 {
     match endpoint {
-        Endpoint::Inet(inet) => serve_inet(inet, app, token, f).await,
-        Endpoint::Unix(unix) => serve_unix(unix, app, token, f).await,
+        Endpoint::Inet(inet) => {
+            serve_inet(&inet, app, token, f, shutdown_timeout).await
+        }
+        Endpoint::Unix(unix) => {
+            serve_unix(&unix, app, token, f, shutdown_timeout).await
+        }
+        Endpoint::Systemd(socket) => {
+            serve_systemd(socket, app, token, f, shutdown_timeout).await
+        }
     }
 }
+
+/// Serves the same [`Router`] over multiple endpoints concurrently,
+/// sharing one [`CancellationToken`] so a single shutdown signal stops
+/// every listener at once.
+///
+/// If any endpoint fails to bind, `token` is cancelled and the already
+/// started listeners are given a chance to drain before the error is
+/// returned, rather than leaving part of the server running unannounced.
+pub async fn serve_all<F>(
+    endpoints: impl IntoIterator<Item = Endpoint>,
+    app: Router,
+    token: CancellationToken,
+    mut f: F,
+    shutdown_timeout: Duration,
+) -> Result<JoinHandle<()>, String>
+where
+    F: FnMut(Option<SocketAddr>),
+{
+    let mut handles = Vec::new();
+
+    for endpoint in endpoints {
+        match serve(
+            endpoint,
+            app.clone(),
+            token.clone(),
+            &mut f,
+            shutdown_timeout,
+        )
+        .await
+        {
+            Ok(handle) => handles.push(handle),
+            Err(err) => {
+                token.cancel();
+
+                for handle in handles {
+                    let _ = handle.await;
+                }
+
+                return Err(err);
+            }
+        }
+    }
+
+    Ok(tokio::spawn(async move {
+        for handle in handles {
+            if let Err(err) = handle.await {
+                error!("Endpoint task panicked: {err}");
+            }
+        }
+    }))
+}
@@ -0,0 +1,128 @@
+use nix::{
+    fcntl::{fcntl, FcntlArg, OFlag},
+    sys::socket::{
+        getsockname, getsockopt, sockopt, AddressFamily, SockType,
+        SockaddrLike, SockaddrStorage,
+    },
+};
+use std::{
+    env,
+    os::fd::{BorrowedFd, FromRawFd, RawFd},
+    result,
+};
+use tokio::net::{TcpListener, UnixListener};
+
+/// First file descriptor number passed by systemd socket activation
+/// (`SD_LISTEN_FDS_START`).
+const LISTEN_FDS_START: RawFd = 3;
+
+type Result<T> = result::Result<T, String>;
+
+/// A socket inherited from an init system rather than bound by this
+/// process, adopted via the systemd socket activation protocol.
+#[derive(Debug)]
+pub enum InheritedSocket {
+    Inet(TcpListener),
+    Unix(UnixListener),
+}
+
+/// Adopts every socket passed to this process via the `LISTEN_FDS`
+/// environment variable, after checking that `LISTEN_PID` names this
+/// process.
+///
+/// Returns an empty `Vec` if the process was not started under socket
+/// activation.
+pub(crate) fn inherited_sockets() -> Result<Vec<InheritedSocket>> {
+    let Some(count) = listen_fds()? else {
+        return Ok(Vec::new());
+    };
+
+    (0..count)
+        .map(|i| inherit(LISTEN_FDS_START + i as RawFd))
+        .collect()
+}
+
+fn listen_fds() -> Result<Option<usize>> {
+    let Ok(pid) = env::var("LISTEN_PID") else {
+        return Ok(None);
+    };
+
+    let pid: i32 = pid
+        .parse()
+        .map_err(|err| format!("Invalid LISTEN_PID '{pid}': {err}"))?;
+
+    if pid != std::process::id() as i32 {
+        return Ok(None);
+    }
+
+    let Ok(fds) = env::var("LISTEN_FDS") else {
+        return Ok(None);
+    };
+
+    fds.parse()
+        .map(Some)
+        .map_err(|err| format!("Invalid LISTEN_FDS '{fds}': {err}"))
+}
+
+fn inherit(fd: RawFd) -> Result<InheritedSocket> {
+    set_nonblocking(fd).map_err(|err| {
+        format!("Failed to set inherited fd {fd} non-blocking: {err}")
+    })?;
+
+    let family = getsockname::<SockaddrStorage>(fd)
+        .map_err(|err| {
+            format!("Failed to inspect inherited fd {fd}: {err}")
+        })?
+        .family();
+
+    let borrowed_fd = unsafe { BorrowedFd::borrow_raw(fd) };
+    let sock_type =
+        getsockopt(&borrowed_fd, sockopt::SockType).map_err(|err| {
+            format!("Failed to inspect inherited fd {fd}: {err}")
+        })?;
+
+    if sock_type != SockType::Stream {
+        return Err(format!(
+            "Inherited fd {fd} is not a supported socket type"
+        ));
+    }
+
+    match family {
+        Some(AddressFamily::Inet) | Some(AddressFamily::Inet6) => {
+            let listener =
+                unsafe { std::net::TcpListener::from_raw_fd(fd) };
+
+            TcpListener::from_std(listener)
+                .map(InheritedSocket::Inet)
+                .map_err(|err| {
+                    format!(
+                        "Failed to adopt inherited TCP socket (fd {fd}): \
+                        {err}"
+                    )
+                })
+        }
+        Some(AddressFamily::Unix) => {
+            let listener =
+                unsafe { std::os::unix::net::UnixListener::from_raw_fd(fd) };
+
+            UnixListener::from_std(listener)
+                .map(InheritedSocket::Unix)
+                .map_err(|err| {
+                    format!(
+                        "Failed to adopt inherited Unix domain socket \
+                        (fd {fd}): {err}"
+                    )
+                })
+        }
+        _ => Err(format!(
+            "Inherited fd {fd} is not a supported socket type"
+        )),
+    }
+}
+
+fn set_nonblocking(fd: RawFd) -> nix::Result<()> {
+    let flags = fcntl(fd, FcntlArg::F_GETFL)?;
+    let flags = OFlag::from_bits_truncate(flags) | OFlag::O_NONBLOCK;
+
+    fcntl(fd, FcntlArg::F_SETFL(flags)).map(|_| ())
+}
@@ -0,0 +1,337 @@
+#![cfg(feature = "tls")]
+
+use rustls::{
+    crypto::CryptoProvider,
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    ServerConfig,
+};
+#[cfg(feature = "serde")]
+use serde::{
+    ser::{Error as SerError, SerializeMap},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{
+    collections::HashMap, fmt, fs::File, io::BufReader, path::PathBuf,
+    result, sync::Arc,
+};
+use tokio_rustls::TlsAcceptor;
+
+type Result<T> = result::Result<T, String>;
+
+/// Paths to a PEM-encoded certificate chain and private key.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CertAndKey {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+impl CertAndKey {
+    fn certified_key(&self) -> Result<CertifiedKey> {
+        let certs = load_certs(&self.cert)?;
+        let key = load_key(&self.key)?;
+
+        // Mirrors what `ServerConfig::builder()` does internally, so a
+        // certificate loaded ahead of the builder (as `Sni`/`Resolver`
+        // configs need to) sees the same crate-features-installed
+        // provider rather than requiring one already be installed.
+        let provider = CryptoProvider::get_default_or_install_from_crate_features();
+
+        let signing_key =
+            provider.key_provider.load_private_key(key).map_err(|err| {
+                format!(
+                    "Unsupported private key in '{}': {err}",
+                    self.key.display()
+                )
+            })?;
+
+        Ok(CertifiedKey::new(certs, signing_key))
+    }
+}
+
+/// Resolves the certificate presented for a TLS connection, optionally
+/// based on the hostname the client requested via SNI.
+///
+/// Implement this to plug in custom certificate storage, e.g. one that
+/// reloads certificates from disk without restarting the server. The
+/// built-in [`TlsConfig::Sni`] variant covers the common case of a
+/// static hostname-to-certificate map.
+pub trait CertResolver: fmt::Debug + Send + Sync {
+    fn resolve(&self, server_name: Option<&str>) -> Option<Arc<CertifiedKey>>;
+}
+
+/// How TLS certificates are selected for an
+/// [`Endpoint::Inet`](crate::Endpoint::Inet) connection.
+#[derive(Debug)]
+pub enum TlsConfig {
+    /// A single certificate used for every connection.
+    Cert(CertAndKey),
+
+    /// Certificates selected per-connection by the hostname presented in
+    /// the ClientHello, falling back to `default` (a key into
+    /// `certificates`) when the client didn't use SNI or named an
+    /// unrecognized hostname.
+    Sni {
+        certificates: HashMap<String, CertAndKey>,
+        default: Option<String>,
+    },
+
+    /// A user-supplied resolver.
+    Resolver(Arc<dyn CertResolver>),
+}
+
+impl TlsConfig {
+    pub(crate) fn acceptor(&self) -> Result<TlsAcceptor> {
+        let config = match self {
+            Self::Cert(cert) => {
+                let certs = load_certs(&cert.cert)?;
+                let key = load_key(&cert.key)?;
+
+                ServerConfig::builder()
+                    .with_no_client_auth()
+                    .with_single_cert(certs, key)
+                    .map_err(|err| {
+                        format!(
+                            "Failed to build TLS configuration from \
+                            certificate '{}' and key '{}': {err}",
+                            cert.cert.display(),
+                            cert.key.display()
+                        )
+                    })?
+            }
+            Self::Sni {
+                certificates,
+                default,
+            } => {
+                let resolver =
+                    SniResolver::new(certificates, default.as_deref())?;
+
+                ServerConfig::builder()
+                    .with_no_client_auth()
+                    .with_cert_resolver(Arc::new(ResolverAdapter(Arc::new(
+                        resolver,
+                    ))))
+            }
+            Self::Resolver(resolver) => ServerConfig::builder()
+                .with_no_client_auth()
+                .with_cert_resolver(Arc::new(ResolverAdapter(
+                    resolver.clone(),
+                ))),
+        };
+
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for TlsConfig {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Cert(cert) => cert.serialize(serializer),
+            Self::Sni {
+                certificates,
+                default,
+            } => {
+                let mut map = serializer.serialize_map(None)?;
+
+                map.serialize_entry("certificates", certificates)?;
+
+                if let Some(default) = default {
+                    map.serialize_entry("default", default)?;
+                }
+
+                map.end()
+            }
+            Self::Resolver(_) => Err(S::Error::custom(
+                "a custom TLS certificate resolver cannot be serialized",
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for TlsConfig {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Cert(CertAndKey),
+            Sni {
+                certificates: HashMap<String, CertAndKey>,
+                #[serde(default)]
+                default: Option<String>,
+            },
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Cert(cert) => Self::Cert(cert),
+            Repr::Sni {
+                certificates,
+                default,
+            } => Self::Sni {
+                certificates,
+                default,
+            },
+        })
+    }
+}
+
+/// The built-in [`CertResolver`] backing [`TlsConfig::Sni`]: a static map
+/// of hostname to certificate, with an optional fallback.
+#[derive(Debug)]
+struct SniResolver {
+    certificates: HashMap<String, Arc<CertifiedKey>>,
+    default: Option<Arc<CertifiedKey>>,
+}
+
+impl SniResolver {
+    fn new(
+        certificates: &HashMap<String, CertAndKey>,
+        default: Option<&str>,
+    ) -> Result<Self> {
+        let mut resolved = HashMap::with_capacity(certificates.len());
+
+        for (name, cert) in certificates {
+            resolved.insert(name.clone(), Arc::new(cert.certified_key()?));
+        }
+
+        let default = default
+            .map(|name| {
+                resolved.get(name).cloned().ok_or_else(|| {
+                    format!(
+                        "default TLS hostname '{name}' has no matching \
+                        entry in 'certificates'"
+                    )
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            certificates: resolved,
+            default,
+        })
+    }
+}
+
+impl CertResolver for SniResolver {
+    fn resolve(&self, server_name: Option<&str>) -> Option<Arc<CertifiedKey>> {
+        resolve_by_name(&self.certificates, self.default.as_ref(), server_name)
+            .cloned()
+    }
+}
+
+/// Matches `server_name` against `certificates`, falling back to
+/// `default`. Factored out of [`SniResolver::resolve`] so the
+/// SNI-match/fallback logic can be tested without a real certificate.
+fn resolve_by_name<'a, T>(
+    certificates: &'a HashMap<String, T>,
+    default: Option<&'a T>,
+    server_name: Option<&str>,
+) -> Option<&'a T> {
+    server_name
+        .and_then(|name| certificates.get(name))
+        .or(default)
+}
+
+/// Adapts a [`CertResolver`] to rustls's [`ResolvesServerCert`].
+struct ResolverAdapter(Arc<dyn CertResolver>);
+
+impl fmt::Debug for ResolverAdapter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl ResolvesServerCert for ResolverAdapter {
+    fn resolve(
+        &self,
+        client_hello: ClientHello<'_>,
+    ) -> Option<Arc<CertifiedKey>> {
+        self.0.resolve(client_hello.server_name())
+    }
+}
+
+fn load_certs(path: &PathBuf) -> Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path).map_err(|err| {
+        format!(
+            "Failed to open certificate file '{}': {err}",
+            path.display()
+        )
+    })?;
+
+    rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<result::Result<Vec<_>, _>>()
+        .map_err(|err| {
+            format!(
+                "Failed to read certificate chain from '{}': {err}",
+                path.display()
+            )
+        })
+}
+
+fn load_key(path: &PathBuf) -> Result<PrivateKeyDer<'static>> {
+    let file = File::open(path).map_err(|err| {
+        format!("Failed to open private key file '{}': {err}", path.display())
+    })?;
+
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|err| {
+            format!(
+                "Failed to read private key from '{}': {err}",
+                path.display()
+            )
+        })?
+        .ok_or_else(|| {
+            format!("No private key found in '{}'", path.display())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_by_name;
+    use std::collections::HashMap;
+
+    fn certificates() -> HashMap<String, i32> {
+        HashMap::from([
+            ("a.example".to_string(), 1),
+            ("b.example".to_string(), 2),
+        ])
+    }
+
+    #[test]
+    fn resolve_by_name_matches_requested_hostname() {
+        assert_eq!(
+            resolve_by_name(&certificates(), None, Some("b.example")),
+            Some(&2)
+        );
+    }
+
+    #[test]
+    fn resolve_by_name_falls_back_on_unrecognized_hostname() {
+        assert_eq!(
+            resolve_by_name(&certificates(), Some(&2), Some("unknown")),
+            Some(&2)
+        );
+    }
+
+    #[test]
+    fn resolve_by_name_falls_back_without_sni() {
+        assert_eq!(resolve_by_name(&certificates(), Some(&1), None), Some(&1));
+    }
+
+    #[test]
+    fn resolve_by_name_returns_none_without_match_or_default() {
+        assert_eq!(
+            resolve_by_name(&certificates(), None, Some("unknown")),
+            None
+        );
+    }
+}